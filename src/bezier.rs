@@ -0,0 +1,71 @@
+//! Cubic/quadratic Bezier flattening shared by every curve-to-polyline
+//! call site, generic over point type so `text.rs` (font-kit's
+//! `Vector2F`) and `svg.rs` (`geom::Point`) don't each carry their own
+//! copy of the same de Casteljau subdivision.
+
+/// The point arithmetic the flattener needs: midpoint interpolation for
+/// subdivision, and perpendicular deviation from a chord for the flatness
+/// test.
+pub(crate) trait FlattenPoint: Copy {
+    fn lerp(self, other: Self, t: f32) -> Self;
+    /// Perpendicular distance from `self` to the line through `a` and `b`
+    /// (or the distance to `a` if `a == b`).
+    fn deviation(self, a: Self, b: Self) -> f32;
+}
+
+/// Flattens the cubic Bezier `start..end` (controls `c1`, `c2`) into a
+/// polyline appended to `out`, recursing until each half is within
+/// `tolerance` of its chord or `max_depth` is reached.
+pub(crate) fn flatten_cubic<P: FlattenPoint>(
+    start: P,
+    c1: P,
+    c2: P,
+    end: P,
+    tolerance: f32,
+    max_depth: u32,
+    out: &mut Vec<P>,
+) {
+    flatten_cubic_rec(start, c1, c2, end, tolerance, max_depth, 0, out);
+    out.push(end);
+}
+
+fn flatten_cubic_rec<P: FlattenPoint>(
+    start: P,
+    c1: P,
+    c2: P,
+    end: P,
+    tolerance: f32,
+    max_depth: u32,
+    depth: u32,
+    out: &mut Vec<P>,
+) {
+    if depth >= max_depth
+        || (c1.deviation(start, end) <= tolerance && c2.deviation(start, end) <= tolerance)
+    {
+        return;
+    }
+    let p01 = start.lerp(c1, 0.5);
+    let p12 = c1.lerp(c2, 0.5);
+    let p23 = c2.lerp(end, 0.5);
+    let p012 = p01.lerp(p12, 0.5);
+    let p123 = p12.lerp(p23, 0.5);
+    let mid = p012.lerp(p123, 0.5);
+    flatten_cubic_rec(start, p01, p012, mid, tolerance, max_depth, depth + 1, out);
+    out.push(mid);
+    flatten_cubic_rec(mid, p123, p23, end, tolerance, max_depth, depth + 1, out);
+}
+
+/// Elevates a quadratic (single control `ctrl`) to the equivalent cubic and
+/// flattens that, since every call site already has a cubic flattener.
+pub(crate) fn flatten_quadratic<P: FlattenPoint>(
+    start: P,
+    ctrl: P,
+    end: P,
+    tolerance: f32,
+    max_depth: u32,
+    out: &mut Vec<P>,
+) {
+    let c1 = start.lerp(ctrl, 2.0 / 3.0);
+    let c2 = end.lerp(ctrl, 2.0 / 3.0);
+    flatten_cubic(start, c1, c2, end, tolerance, max_depth, out);
+}
@@ -0,0 +1,211 @@
+use geom::Point;
+
+/// A color with red, green, blue, and alpha channels in `[0.0, 1.0]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct V4 {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl V4 {
+    pub fn new(r: f32, g: f32, b: f32, a: f32) -> Self { V4 { r, g, b, a } }
+
+    fn lerp(self, other: V4, t: f32) -> V4 {
+        V4::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+            self.a + (other.a - self.a) * t,
+        )
+    }
+}
+
+/// A color stop in a gradient, at ratio `0.0..=1.0` along the gradient.
+#[derive(Debug, Clone, Copy)]
+pub struct Stop {
+    pub ratio: f32,
+    pub color: V4,
+}
+
+impl Stop {
+    pub fn new(ratio: f32, color: V4) -> Self { Stop { ratio, color } }
+}
+
+/// How a gradient's parameter `t` is folded back into `[0, 1]` once it runs
+/// past the defined stops.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Spread {
+    /// Clamp `t` to `[0, 1]`, holding the end stops' colors beyond the ends.
+    Pad,
+    /// Tile the gradient by taking `fract(t)`.
+    Repeat,
+    /// Tile the gradient as a triangle wave, mirroring every other repeat.
+    Reflect,
+}
+
+impl Spread {
+    fn fold(&self, t: f32) -> f32 {
+        match *self {
+            Spread::Pad => t.max(0.0).min(1.0),
+            Spread::Repeat => t.rem_euclid(1.0),
+            Spread::Reflect => {
+                let t = t.rem_euclid(2.0);
+                (t - 1.0).abs()
+            }
+        }
+    }
+}
+
+/// Stops sorted by ascending `ratio`, sampled by a folded gradient parameter.
+#[derive(Debug, Clone)]
+pub struct Stops(Vec<Stop>);
+
+impl Stops {
+    /// Builds a `Stops` list, sorting by ratio.
+    ///
+    /// Panics if `stops` is empty; a gradient needs at least one color.
+    pub fn new(mut stops: Vec<Stop>) -> Self {
+        assert!(!stops.is_empty(), "a gradient needs at least one stop");
+        stops.sort_by(|a, b| a.ratio.partial_cmp(&b.ratio).unwrap());
+        Stops(stops)
+    }
+
+    fn sample(&self, t: f32) -> V4 {
+        let stops = &self.0;
+        if stops.len() == 1 || t <= stops[0].ratio {
+            return stops[0].color;
+        }
+        if t >= stops[stops.len() - 1].ratio {
+            return stops[stops.len() - 1].color;
+        }
+        for window in stops.windows(2) {
+            let (lo, hi) = (window[0], window[1]);
+            if t >= lo.ratio && t <= hi.ratio {
+                let span = hi.ratio - lo.ratio;
+                let local_t = if span > 0.0 { (t - lo.ratio) / span } else { 0.0 };
+                return lo.color.lerp(hi.color, local_t);
+            }
+        }
+        stops[stops.len() - 1].color
+    }
+}
+
+/// A linear gradient between `start` and `end`, sampled by projecting a
+/// point onto the `start -> end` axis.
+#[derive(Debug, Clone)]
+pub struct LinearGradient {
+    pub start: Point,
+    pub end: Point,
+    pub stops: Stops,
+    pub spread: Spread,
+}
+
+impl LinearGradient {
+    fn color(&self, p: Point) -> V4 {
+        let dx = self.end.x - self.start.x;
+        let dy = self.end.y - self.start.y;
+        let len_sq = dx * dx + dy * dy;
+        let t = if len_sq > 0.0 {
+            ((p.x - self.start.x) * dx + (p.y - self.start.y) * dy) / len_sq
+        } else {
+            0.0
+        };
+        self.stops.sample(self.spread.fold(t))
+    }
+}
+
+/// A radial gradient centered at `center`, sampled by distance to `radius`.
+#[derive(Debug, Clone)]
+pub struct RadialGradient {
+    pub center: Point,
+    pub radius: f32,
+    pub stops: Stops,
+    pub spread: Spread,
+}
+
+impl RadialGradient {
+    fn color(&self, p: Point) -> V4 {
+        let dx = p.x - self.center.x;
+        let dy = p.y - self.center.y;
+        let dist = (dx * dx + dy * dy).sqrt();
+        let t = if self.radius > 0.0 { dist / self.radius } else { 0.0 };
+        self.stops.sample(self.spread.fold(t))
+    }
+}
+
+/// Maps a `Point` to a color, either flat, procedural, or interpolated
+/// across a gradient.
+#[derive(Clone)]
+pub enum Colorer {
+    Solid(V4),
+    Linear(LinearGradient),
+    Radial(RadialGradient),
+}
+
+impl Colorer {
+    pub fn color(&self, p: Point) -> V4 {
+        match *self {
+            Colorer::Solid(color) => color,
+            Colorer::Linear(ref gradient) => gradient.color(p),
+            Colorer::Radial(ref gradient) => gradient.color(p),
+        }
+    }
+}
+
+impl From<V4> for Colorer {
+    fn from(color: V4) -> Self { Colorer::Solid(color) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pad_clamps_to_unit_range() {
+        assert_eq!(Spread::Pad.fold(-0.5), 0.0);
+        assert_eq!(Spread::Pad.fold(0.5), 0.5);
+        assert_eq!(Spread::Pad.fold(1.5), 1.0);
+    }
+
+    #[test]
+    fn repeat_tiles_by_fractional_part() {
+        assert_eq!(Spread::Repeat.fold(0.25), 0.25);
+        assert_eq!(Spread::Repeat.fold(1.25), 0.25);
+        assert_eq!(Spread::Repeat.fold(-0.25), 0.75);
+    }
+
+    #[test]
+    fn reflect_mirrors_every_other_repeat() {
+        assert_eq!(Spread::Reflect.fold(0.0), 1.0);
+        assert_eq!(Spread::Reflect.fold(0.5), 0.5);
+        assert_eq!(Spread::Reflect.fold(1.0), 0.0);
+        assert_eq!(Spread::Reflect.fold(1.5), 0.5);
+        assert_eq!(Spread::Reflect.fold(2.0), 1.0);
+    }
+
+    fn stop(ratio: f32, gray: f32) -> Stop {
+        Stop::new(ratio, V4::new(gray, gray, gray, 1.0))
+    }
+
+    #[test]
+    fn sample_clamps_before_first_and_after_last_stop() {
+        let stops = Stops::new(vec![stop(0.25, 0.0), stop(0.75, 1.0)]);
+        assert_eq!(stops.sample(0.0), V4::new(0.0, 0.0, 0.0, 1.0));
+        assert_eq!(stops.sample(1.0), V4::new(1.0, 1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn sample_interpolates_between_surrounding_stops() {
+        let stops = Stops::new(vec![stop(0.0, 0.0), stop(1.0, 1.0)]);
+        assert_eq!(stops.sample(0.5), V4::new(0.5, 0.5, 0.5, 1.0));
+    }
+
+    #[test]
+    fn sample_single_stop_is_constant() {
+        let stops = Stops::new(vec![stop(0.5, 0.5)]);
+        assert_eq!(stops.sample(0.0), V4::new(0.5, 0.5, 0.5, 1.0));
+        assert_eq!(stops.sample(1.0), V4::new(0.5, 0.5, 0.5, 1.0));
+    }
+}
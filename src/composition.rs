@@ -8,6 +8,10 @@ use std::rc::Rc;
 pub enum Layer {
     Mesh(Mesh),
     ShadedMesh { shader: Shader, mesh: Mesh },
+    /// `content` is only visible where `mask`'s filled region overlaps it.
+    /// Masks nest: a `Masked` layer may itself appear inside another
+    /// `Masked` layer's `content`.
+    Masked { mask: Mesh, content: Vec<Layer> },
 }
 
 impl Layer {
@@ -31,6 +35,21 @@ impl Layer {
                 shader: wrap_shader(shader),
                 mesh,
             },
+            Layer::Masked { mask, content } => Layer::Masked {
+                mask,
+                content: content
+                    .into_iter()
+                    .map(|layer| Layer::freeze_frame(layer, render_frame))
+                    .collect(),
+            },
+        }
+    }
+
+    /// Masks `content` by the filled region of `mask`.
+    pub fn masked<L: Into<LayerInput>>(mask: Mesh, content: L) -> Layer {
+        Layer::Masked {
+            mask,
+            content: content.into().collect(),
         }
     }
 }
@@ -110,6 +129,10 @@ impl Composition {
         self.add(Mesh::from(Rect::frame()).with_colorer(colorer))
     }
 
+    pub fn masked<L: Into<LayerInput>>(self, mask: Mesh, content: L) -> Self {
+        self.add(Layer::masked(mask, content))
+    }
+
     pub fn add<L: Into<LayerInput>>(mut self, layer: L) -> Self {
         self.layers.extend(layer.into());
         self
@@ -0,0 +1,68 @@
+//! GPU-facing types shared by the tessellation, shading, and render
+//! modules: the vertex format meshes are tessellated into, and the
+//! `Factory` pattern used to turn a CPU-side spec into the GPU resource
+//! built from it.
+
+pub mod programs;
+pub mod render;
+pub mod shaders;
+pub mod tessellation;
+
+pub use self::shaders::{GpuShader, Shader};
+
+use color::V4;
+use errors::Result;
+use geom::Point;
+use glium::backend::glutin::headless::Headless;
+use glium::{index::IndexBuffer, VertexBuffer};
+use std::rc::Rc;
+
+/// The headless GL context every GPU resource is created against.
+pub type Gpu = Headless;
+
+/// Builds a GPU-resident resource from a CPU-side spec (a `Mesh`, a
+/// `Layer`, ...), against an active `Gpu` context.
+pub trait Factory<Spec> {
+    fn produce(spec: Spec, gpu: Rc<Gpu>) -> Result<Self>
+    where
+        Self: Sized;
+}
+
+/// One tessellated vertex: position and color in mesh space, plus a
+/// texture UV. The UV is only meaningful for meshes drawn with a
+/// `shaders::BitmapFill`; everything else tessellates it to `(0.0, 0.0)`.
+#[derive(Debug, Clone, Copy)]
+pub struct GpuVertex {
+    pub position: (f32, f32),
+    pub color: (f32, f32, f32, f32),
+    pub uv: (f32, f32),
+}
+
+glium::implement_vertex!(GpuVertex, position, color, uv);
+
+impl From<(Point, V4)> for GpuVertex {
+    fn from((point, color): (Point, V4)) -> Self {
+        GpuVertex {
+            position: (point.x, point.y),
+            color: (color.r, color.g, color.b, color.a),
+            uv: (0.0, 0.0),
+        }
+    }
+}
+
+/// A vertex normal, carried alongside `GpuVertex` in a parallel buffer
+/// rather than packed into it, matching how `Tessellation` emits the two.
+#[derive(Debug, Clone, Copy)]
+pub struct GpuNormal {
+    pub normal: (f32, f32, f32),
+}
+
+glium::implement_vertex!(GpuNormal, normal);
+
+/// A tessellated mesh uploaded to GPU buffers, plus the per-frame scale
+/// tweened from its source `Mesh`.
+pub struct GpuMesh {
+    pub vertex_buffer: VertexBuffer<GpuVertex>,
+    pub index_buffer: IndexBuffer<u32>,
+    pub scale: f32,
+}
@@ -0,0 +1,201 @@
+//! Compiles and owns the GL programs every `GpuShader` variant draws
+//! with, and the lower-level stencil-buffer operations `Buffer::draw`
+//! uses to rasterize and clear `Layer::Masked` masks.
+
+use errors::Result;
+use glium::draw_parameters::{StencilOperation, StencilTest};
+use glium::texture::Texture2d;
+use glium::uniforms::Sampler;
+use glium::{uniform, Blend, DrawParameters, Program, Surface};
+use gpu::shaders::{GpuShader, StencilState};
+use gpu::{Gpu, GpuMesh};
+
+const VERTEX_SHADER: &str = r#"
+    #version 140
+    in vec2 position;
+    in vec4 color;
+    in vec2 uv;
+    out vec4 v_color;
+    out vec2 v_uv;
+    uniform float scale;
+    void main() {
+        v_color = color;
+        v_uv = uv;
+        gl_Position = vec4(position * scale, 0.0, 1.0);
+    }
+"#;
+
+const SOLID_FRAGMENT_SHADER: &str = r#"
+    #version 140
+    in vec4 v_color;
+    out vec4 f_color;
+    uniform vec4 constant_color;
+    void main() { f_color = v_color * constant_color; }
+"#;
+
+const BITMAP_FRAGMENT_SHADER: &str = r#"
+    #version 140
+    in vec4 v_color;
+    in vec2 v_uv;
+    out vec4 f_color;
+    uniform sampler2D tex;
+    void main() { f_color = texture(tex, v_uv) * v_color; }
+"#;
+
+/// Writes no color; used while rasterizing a mask into the stencil buffer.
+const STENCIL_FRAGMENT_SHADER: &str = r#"
+    #version 140
+    void main() { }
+"#;
+
+/// The compiled programs behind every `GpuShader` variant.
+pub struct Library {
+    solid: Program,
+    bitmap: Program,
+    stencil: Program,
+}
+
+impl Library {
+    pub fn compile(gpu: &Gpu) -> Result<Self> {
+        Ok(Library {
+            solid: Program::from_source(gpu, VERTEX_SHADER, SOLID_FRAGMENT_SHADER, None)?,
+            bitmap: Program::from_source(gpu, VERTEX_SHADER, BITMAP_FRAGMENT_SHADER, None)?,
+            stencil: Program::from_source(gpu, VERTEX_SHADER, STENCIL_FRAGMENT_SHADER, None)?,
+        })
+    }
+
+    /// Draws every `GpuShader` variant that doesn't need its own sampler;
+    /// `GpuShader::draw` binds bitmap fills' `Sampler` itself and calls
+    /// `draw_bitmap_fill` instead.
+    pub fn draw(
+        &self,
+        shader: &GpuShader,
+        _frame: usize,
+        surface: &mut impl Surface,
+        mesh: &GpuMesh,
+        _blend_target: Option<&Texture2d>,
+        stencil: Option<StencilState>,
+    ) -> Result<()> {
+        let color = match shader {
+            GpuShader::Solid(color) => (color.r, color.g, color.b, color.a),
+            _ => (1.0, 1.0, 1.0, 1.0),
+        };
+        surface
+            .draw(
+                &mesh.vertex_buffer,
+                &mesh.index_buffer,
+                &self.solid,
+                &uniform! { scale: mesh.scale, constant_color: color },
+                &content_draw_parameters(stencil),
+            )
+            .map_err(Into::into)
+    }
+
+    /// Draws `mesh` sampling `sampler` for its fill color, modulated by the
+    /// mesh's per-vertex color.
+    pub fn draw_bitmap_fill(
+        &self,
+        surface: &mut impl Surface,
+        mesh: &GpuMesh,
+        sampler: Sampler<Texture2d>,
+        _blend_target: Option<&Texture2d>,
+        stencil: Option<StencilState>,
+    ) -> Result<()> {
+        surface
+            .draw(
+                &mesh.vertex_buffer,
+                &mesh.index_buffer,
+                &self.bitmap,
+                &uniform! { scale: mesh.scale, tex: sampler },
+                &content_draw_parameters(stencil),
+            )
+            .map_err(Into::into)
+    }
+
+    /// Rasterizes `mesh` into the stencil buffer, setting `write_bit`
+    /// wherever it's filled and the stencil already carries every bit in
+    /// `test_mask` (so a nested mask only extends the region its enclosing
+    /// mask already clipped to). Writes no color.
+    pub fn draw_stencil_mask(
+        &self,
+        surface: &mut impl Surface,
+        mesh: &GpuMesh,
+        write_bit: u32,
+        test_mask: u32,
+    ) -> Result<()> {
+        let test = if test_mask == 0 {
+            StencilTest::AlwaysPass
+        } else {
+            StencilTest::IfEqual { mask: test_mask }
+        };
+        let params = DrawParameters {
+            color_mask: (false, false, false, false),
+            depth_write: false,
+            stencil_test_clockwise: test,
+            stencil_test_counter_clockwise: test,
+            stencil_reference_value_clockwise: test_mask as i32,
+            stencil_reference_value_counter_clockwise: test_mask as i32,
+            stencil_write_mask_clockwise: write_bit,
+            stencil_write_mask_counter_clockwise: write_bit,
+            stencil_pass_depth_pass_clockwise: StencilOperation::Replace,
+            stencil_pass_depth_pass_counter_clockwise: StencilOperation::Replace,
+            ..Default::default()
+        };
+        surface
+            .draw(
+                &mesh.vertex_buffer,
+                &mesh.index_buffer,
+                &self.stencil,
+                &uniform! { scale: mesh.scale },
+                &params,
+            )
+            .map_err(Into::into)
+    }
+
+    /// Clears `write_bit` back out of the stencil buffer over `mesh`'s
+    /// filled region, undoing `draw_stencil_mask` once a `Layer::Masked`
+    /// group finishes drawing so the bit can be reused by a sibling mask at
+    /// the same nesting depth.
+    pub fn clear_stencil_mask(&self, surface: &mut impl Surface, mesh: &GpuMesh, write_bit: u32) -> Result<()> {
+        let params = DrawParameters {
+            color_mask: (false, false, false, false),
+            depth_write: false,
+            stencil_test_clockwise: StencilTest::IfEqual { mask: write_bit },
+            stencil_test_counter_clockwise: StencilTest::IfEqual { mask: write_bit },
+            stencil_reference_value_clockwise: write_bit as i32,
+            stencil_reference_value_counter_clockwise: write_bit as i32,
+            stencil_write_mask_clockwise: write_bit,
+            stencil_write_mask_counter_clockwise: write_bit,
+            stencil_pass_depth_pass_clockwise: StencilOperation::Zero,
+            stencil_pass_depth_pass_counter_clockwise: StencilOperation::Zero,
+            ..Default::default()
+        };
+        surface
+            .draw(
+                &mesh.vertex_buffer,
+                &mesh.index_buffer,
+                &self.stencil,
+                &uniform! { scale: mesh.scale },
+                &params,
+            )
+            .map_err(Into::into)
+    }
+}
+
+fn content_draw_parameters(stencil: Option<StencilState>) -> DrawParameters<'static> {
+    let blend = Blend::alpha_blending();
+    match stencil {
+        Some(state) => {
+            let test = StencilTest::IfEqual { mask: state.test_mask };
+            DrawParameters {
+                blend,
+                stencil_test_clockwise: test,
+                stencil_test_counter_clockwise: test,
+                stencil_reference_value_clockwise: state.test_mask as i32,
+                stencil_reference_value_counter_clockwise: state.test_mask as i32,
+                ..Default::default()
+            }
+        }
+        None => DrawParameters { blend, ..Default::default() },
+    }
+}
@@ -1,49 +1,103 @@
 use gpu::{Factory, Gpu, GpuMesh};
 use std::rc::Rc;
 use composition::{Composition, Layer};
-use gpu::shaders::{GpuShader, Shader};
+use gpu::shaders::{GpuShader, Shader, StencilState};
 use mesh::Mesh;
 use glium::Surface;
-use glium::texture::{MipmapsOption, Texture2d, UncompressedFloatFormat};
+use glium::framebuffer::{DepthStencilRenderBuffer, SimpleFrameBuffer};
+use glium::texture::{DepthStencilFormat, MipmapsOption, Texture2d, UncompressedFloatFormat};
 use glium::uniforms::MagnifySamplerFilter;
 use errors::Result;
 use poly::Rect;
 use gpu::programs::Library;
 
-pub struct GpuLayer {
-    src: Mesh,
-    shader: GpuShader,
-    cached_mesh: GpuMesh,
+pub enum GpuLayer {
+    Shaded {
+        src: Mesh,
+        shader: GpuShader,
+        cached_mesh: GpuMesh,
+    },
+    /// `content` is only drawn where `mask`'s filled region, rendered into
+    /// the stencil buffer, overlaps it.
+    Masked { mask: GpuMesh, content: Vec<GpuLayer> },
 }
 
 impl Factory<Layer> for GpuLayer {
     fn produce(spec: Layer, gpu: Rc<Gpu>) -> Result<GpuLayer> {
-        let (shader, mesh) = match spec {
-            Layer::Mesh(mesh) => (Shader::Default, mesh),
-            Layer::ShadedMesh { shader, mesh } => (shader, mesh),
-        };
-        Ok(GpuLayer {
-            shader: GpuShader::produce(shader, gpu.clone())?,
-            cached_mesh: GpuMesh::produce(mesh.clone(), gpu.clone())?,
-            src: mesh,
+        Ok(match spec {
+            Layer::Mesh(mesh) => GpuLayer::Shaded {
+                shader: GpuShader::produce(Shader::Default, gpu.clone())?,
+                cached_mesh: GpuMesh::produce(mesh.clone(), gpu)?,
+                src: mesh,
+            },
+            Layer::ShadedMesh { shader, mesh } => GpuLayer::Shaded {
+                shader: GpuShader::produce(shader, gpu.clone())?,
+                cached_mesh: GpuMesh::produce(mesh.clone(), gpu)?,
+                src: mesh,
+            },
+            Layer::Masked { mask, content } => GpuLayer::Masked {
+                mask: GpuMesh::produce(mask, gpu.clone())?,
+                content: content
+                    .into_iter()
+                    .map(|layer| GpuLayer::produce(layer, gpu.clone()))
+                    .collect::<Result<Vec<GpuLayer>>>()?,
+            },
         })
     }
 }
 
 impl GpuLayer {
-    pub fn step(mut self, frame: usize) -> Result<Self> {
-        self.cached_mesh.scale = self.src.scale.tween(frame);
-        Ok(self)
+    pub fn step(self, frame: usize) -> Result<Self> {
+        Ok(match self {
+            GpuLayer::Shaded { src, shader, mut cached_mesh } => {
+                cached_mesh.scale = src.scale.tween(frame);
+                GpuLayer::Shaded { src, shader, cached_mesh }
+            }
+            GpuLayer::Masked { mask, content } => GpuLayer::Masked {
+                mask,
+                content: content
+                    .into_iter()
+                    .map(|l| l.step(frame))
+                    .collect::<Result<Vec<GpuLayer>>>()?,
+            },
+        })
     }
-    pub fn render<'a>(&'a self) -> (&'a GpuShader, &'a GpuMesh) {
-        (&self.shader, &self.cached_mesh)
+
+    /// Flattens this layer (and, recursively, any masked content) into the
+    /// ordered push/draw/pop command stream `Buffer::draw` replays.
+    fn collect_cmds<'a>(&'a self, cmds: &mut Vec<DrawCmd<'a>>) {
+        match self {
+            GpuLayer::Shaded { shader, cached_mesh, .. } => {
+                cmds.push(DrawCmd::Draw(shader, cached_mesh));
+            }
+            GpuLayer::Masked { mask, content } => {
+                cmds.push(DrawCmd::PushMask(mask));
+                for layer in content {
+                    layer.collect_cmds(cmds);
+                }
+                cmds.push(DrawCmd::PopMask(mask));
+            }
+        }
     }
 }
 
+/// One step of the flattened draw command stream a `Composition`'s layer
+/// tree compiles down to.
+enum DrawCmd<'a> {
+    Draw(&'a GpuShader, &'a GpuMesh),
+    /// Rasterize `mesh` into the stencil buffer at the next nesting depth,
+    /// then restrict subsequent draws (until the matching `PopMask`) to the
+    /// region it covers.
+    PushMask(&'a GpuMesh),
+    /// Carries the same mask mesh `PushMask` rasterized, so its bit can be
+    /// cleared back out of the stencil buffer over the same region.
+    PopMask(&'a GpuMesh),
+}
+
 pub struct DrawCtx<'a, 'b> {
     frame: usize,
     library: &'b Library,
-    cmds: Vec<(&'a GpuShader, &'a GpuMesh)>,
+    cmds: Vec<DrawCmd<'a>>,
 }
 
 struct BufferSpec {
@@ -52,7 +106,13 @@ struct BufferSpec {
 }
 
 struct Buffer {
+    gpu: Rc<Gpu>,
     targets: [Rc<Texture2d>; 2],
+    // A stencil attachment per target: `Texture2d::as_surface()` only
+    // attaches a color buffer, so without this `draw_stencil_mask`/
+    // `clear_stencil_mask`'s stencil test would run against a framebuffer
+    // with no stencil plane at all and never actually clip anything.
+    stencils: [DepthStencilRenderBuffer; 2],
     blitter: (GpuShader, GpuMesh),
 }
 
@@ -61,19 +121,57 @@ impl Buffer {
         vec![(&self.blitter.0, &self.blitter.1)]
     }
 
+    fn framebuffer(&self, index: usize) -> Result<SimpleFrameBuffer> {
+        SimpleFrameBuffer::with_depth_and_stencil_buffer(
+            self.gpu.as_ref(),
+            self.targets[index].as_ref(),
+            &self.stencils[index],
+        ).map_err(Into::into)
+    }
+
     /// Draws commands to the buffer and returns a set of commands to draw this
-    /// buffer to screen.
+    /// buffer to screen. `Layer::Masked` groups compile down to a
+    /// `PushMask`/`PopMask` pair around their content; each nesting level
+    /// gets its own stencil bit so masks can nest, mirroring the
+    /// `num_masks`/`write_stencil_mask`/`test_stencil_mask` scheme used by
+    /// stencil-based SWF clipping.
     pub fn draw<'a>(&'a self, ctx: DrawCtx) -> Result<Vec<(&'a GpuShader, &'a GpuMesh)>> {
-        let mut surfaces = [self.targets[0].as_surface(), self.targets[1].as_surface()];
-        for (ref shader, ref mesh) in ctx.cmds.into_iter() {
-            shader.draw(
-                ctx.library,
-                ctx.frame,
-                &mut surfaces[0],
-                mesh,
-                Some(self.targets[1].as_ref()),
-            )?;
-            surfaces[0].fill(&surfaces[1], MagnifySamplerFilter::Linear);
+        let mut surfaces = [self.framebuffer(0)?, self.framebuffer(1)?];
+        let mut mask_stack: Vec<StencilState> = Vec::new();
+        for cmd in ctx.cmds.into_iter() {
+            match cmd {
+                DrawCmd::PushMask(mask_mesh) => {
+                    let depth = mask_stack.len() as u32;
+                    let write_bit = 1 << depth;
+                    let enclosing_test = mask_stack.last().map(|s| s.test_mask).unwrap_or(0);
+                    ctx.library.draw_stencil_mask(
+                        &mut surfaces[0],
+                        mask_mesh,
+                        write_bit,
+                        enclosing_test,
+                    )?;
+                    mask_stack.push(StencilState {
+                        write_mask: write_bit,
+                        test_mask: enclosing_test | write_bit,
+                    });
+                }
+                DrawCmd::PopMask(mask_mesh) => {
+                    if let Some(popped) = mask_stack.pop() {
+                        ctx.library.clear_stencil_mask(&mut surfaces[0], mask_mesh, popped.write_mask)?;
+                    }
+                }
+                DrawCmd::Draw(shader, mesh) => {
+                    shader.draw(
+                        ctx.library,
+                        ctx.frame,
+                        &mut surfaces[0],
+                        mesh,
+                        Some(self.targets[1].as_ref()),
+                        mask_stack.last().cloned(),
+                    )?;
+                    surfaces[0].fill(&surfaces[1], MagnifySamplerFilter::Linear);
+                }
+            }
         }
         Ok(self.blitter())
     }
@@ -94,16 +192,30 @@ impl Factory<BufferSpec> for Buffer {
                 spec.height,
             ).map_err(Into::into)
         };
+        let stencil = || -> Result<DepthStencilRenderBuffer> {
+            DepthStencilRenderBuffer::new(
+                gpu.as_ref(),
+                DepthStencilFormat::I24I8,
+                spec.width,
+                spec.height,
+            ).map_err(Into::into)
+        };
 
         let targets = [Rc::new(target()?), Rc::new(target()?)];
-        for target in targets.iter() {
-            target.as_ref().as_surface().clear_color(0.0, 0.0, 0.0, 0.0)
+        let stencils = [stencil()?, stencil()?];
+        for (target, stencil) in targets.iter().zip(stencils.iter()) {
+            SimpleFrameBuffer::with_depth_and_stencil_buffer(
+                gpu.as_ref(),
+                target.as_ref(),
+                stencil,
+            ).map_err(Into::into)?
+                .clear_color_and_stencil(0.0, 0.0, 0.0, 0.0, 0);
         }
         let blitter = (
             GpuShader::Texture(targets[0].clone()),
-            GpuMesh::produce(Mesh::from(Rect::frame()), gpu)?,
+            GpuMesh::produce(Mesh::from(Rect::frame()), gpu.clone())?,
         );
-        Ok(Self { targets, blitter })
+        Ok(Self { gpu, targets, stencils, blitter })
     }
 }
 
@@ -164,7 +276,11 @@ impl Render {
         self.buffer.front()
     }
 
-    fn cmds<'a>(&'a self) -> Vec<(&'a GpuShader, &'a GpuMesh)> {
-        self.layers.iter().map(|l| l.render()).collect()
+    fn cmds<'a>(&'a self) -> Vec<DrawCmd<'a>> {
+        let mut cmds = Vec::new();
+        for layer in self.layers.iter() {
+            layer.collect_cmds(&mut cmds);
+        }
+        cmds
     }
 }
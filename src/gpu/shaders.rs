@@ -0,0 +1,169 @@
+use color::V4;
+use errors::Result;
+use geom::Point;
+use glium::texture::Texture2d;
+use glium::uniforms::SamplerWrapFunction;
+use glium::{Surface, uniforms::MagnifySamplerFilter};
+use gpu::programs::Library;
+use gpu::{Factory, Gpu, GpuMesh, GpuVertex};
+use std::rc::Rc;
+
+/// A 2D affine transform mapping mesh-space positions to texture UVs, row
+/// major: `u = a*x + c*y + e`, `v = b*x + d*y + f`.
+#[derive(Debug, Clone, Copy)]
+pub struct UvTransform {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32,
+}
+
+impl UvTransform {
+    pub fn identity() -> Self {
+        UvTransform { a: 1.0, b: 0.0, c: 0.0, d: 1.0, e: 0.0, f: 0.0 }
+    }
+
+    pub fn apply(&self, p: Point) -> (f32, f32) {
+        (
+            self.a * p.x + self.c * p.y + self.e,
+            self.b * p.x + self.d * p.y + self.f,
+        )
+    }
+}
+
+/// How a bitmap fill samples outside its `[0, 1]` UV range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WrapMode {
+    Clamp,
+    Repeat,
+}
+
+impl WrapMode {
+    fn to_glium(self) -> SamplerWrapFunction {
+        match self {
+            WrapMode::Clamp => SamplerWrapFunction::Clamp,
+            WrapMode::Repeat => SamplerWrapFunction::Repeat,
+        }
+    }
+}
+
+/// A fill style sourced from an image rather than a flat or procedural
+/// color, mapped onto mesh-space via a `UvTransform`.
+#[derive(Clone)]
+pub struct BitmapFill {
+    pub image: Rc<Texture2d>,
+    pub transform: UvTransform,
+    pub wrap: WrapMode,
+}
+
+/// Builds a vertex carrying a texture UV alongside its position and color,
+/// for meshes drawn with a [`BitmapFill`].
+impl From<(Point, V4, (f32, f32))> for GpuVertex {
+    fn from((point, color, uv): (Point, V4, (f32, f32))) -> Self {
+        let mut vertex = GpuVertex::from((point, color));
+        vertex.uv = uv;
+        vertex
+    }
+}
+
+#[derive(Clone)]
+pub enum Shader {
+    Default,
+    Solid(V4),
+    BitmapFill(BitmapFill),
+    Intermittent {
+        src: Rc<Shader>,
+        predicate: Rc<dyn Fn(usize) -> bool>,
+    },
+}
+
+impl From<BitmapFill> for Shader {
+    fn from(fill: BitmapFill) -> Self { Shader::BitmapFill(fill) }
+}
+
+pub enum GpuShader {
+    Default,
+    Solid(V4),
+    Texture(Rc<Texture2d>),
+    BitmapFill(BitmapFill),
+    Intermittent {
+        src: Rc<GpuShader>,
+        predicate: Rc<dyn Fn(usize) -> bool>,
+    },
+}
+
+impl Factory<Shader> for GpuShader {
+    fn produce(spec: Shader, gpu: Rc<Gpu>) -> Result<GpuShader> {
+        Ok(match spec {
+            Shader::Default => GpuShader::Default,
+            Shader::Solid(color) => GpuShader::Solid(color),
+            Shader::BitmapFill(fill) => GpuShader::BitmapFill(fill),
+            Shader::Intermittent { src, predicate } => GpuShader::Intermittent {
+                src: Rc::new(GpuShader::produce((*src).clone(), gpu)?),
+                predicate,
+            },
+        })
+    }
+}
+
+/// A stencil test/write configuration for one nesting level of a
+/// `Layer::Masked` group, mirroring the `num_masks`/`write_stencil_mask`/
+/// `test_stencil_mask` scheme used to clip layers without a full
+/// stencil-buffer reset per mask.
+#[derive(Debug, Clone, Copy)]
+pub struct StencilState {
+    /// Bit written into the stencil buffer while rasterizing a mask shape
+    /// at this nesting depth.
+    pub write_mask: u32,
+    /// Bits that must already be set for masked content at this depth to
+    /// pass the stencil test.
+    pub test_mask: u32,
+}
+
+impl GpuShader {
+    /// Draws `mesh` with this shader. Bitmap fills are sampled here since
+    /// they need a `Sampler` built from their own `WrapMode`; every other
+    /// variant is dispatched to `library`, which owns the compiled
+    /// programs and their uniform bindings. `stencil` restricts the draw to
+    /// the region written by an enclosing `Layer::Masked` mask, if any.
+    pub fn draw(
+        &self,
+        library: &Library,
+        frame: usize,
+        surface: &mut impl Surface,
+        mesh: &GpuMesh,
+        blend_target: Option<&Texture2d>,
+        stencil: Option<StencilState>,
+    ) -> Result<()> {
+        match self {
+            GpuShader::Intermittent { src, predicate } => {
+                if predicate(frame) {
+                    src.draw(library, frame, surface, mesh, blend_target, stencil)
+                } else {
+                    Ok(())
+                }
+            }
+            GpuShader::BitmapFill(fill) => {
+                let sampler = fill
+                    .image
+                    .sampled()
+                    .magnify_filter(MagnifySamplerFilter::Linear)
+                    .wrap_function(fill.wrap.to_glium());
+                library.draw_bitmap_fill(surface, mesh, sampler, blend_target, stencil)
+            }
+            GpuShader::Texture(texture) => {
+                // Full-frame blits (e.g. `Buffer::blitter`) need to sample
+                // the rendered texture, same as a bitmap fill; `library.draw`
+                // only drives the solid program, which never reads `tex`.
+                let sampler = texture
+                    .sampled()
+                    .magnify_filter(MagnifySamplerFilter::Linear)
+                    .wrap_function(WrapMode::Clamp.to_glium());
+                library.draw_bitmap_fill(surface, mesh, sampler, blend_target, stencil)
+            }
+            other => library.draw(other, frame, surface, mesh, blend_target, stencil),
+        }
+    }
+}
@@ -1,13 +1,15 @@
 use color::Colorer;
 use errors::Result;
 use geom::{Ellipse, Point, Poly};
+use gpu::shaders::UvTransform;
 use gpu::{GpuNormal, GpuVertex};
 use lyon::path_iterator::math::Vec2;
 use lyon::tessellation::*;
 use lyon::tessellation::geometry_builder::{VertexBuffers, simple_builder};
 use mesh::DrawMode;
+use stroke::{dash_polyline, StrokeStyle};
 
-const ELLIPSE_TOLERANCE: f32 = 0.00001;
+pub(crate) const ELLIPSE_TOLERANCE: f32 = 0.00001;
 
 #[derive(Debug, Default)]
 pub struct Tessellation {
@@ -17,14 +19,19 @@ pub struct Tessellation {
 }
 
 impl Tessellation {
-    fn from_fill_buffer(buffer: VertexBuffers<FillVertex>, colorer: Colorer) -> Self {
+    fn from_fill_buffer(
+        buffer: VertexBuffers<FillVertex>,
+        colorer: Colorer,
+        uv: Option<UvTransform>,
+    ) -> Self {
         let mut tessellation = Tessellation::default();
         for v in buffer.vertices {
             let point = Point::from(v.position);
             let color = colorer.color(point);
-            tessellation
-                .vertices
-                .push(GpuVertex::from((point, color)));
+            tessellation.vertices.push(match uv {
+                Some(uv) => GpuVertex::from((point, color, uv.apply(point))),
+                None => GpuVertex::from((point, color)),
+            });
             tessellation
                 .normals
                 .push(GpuNormal { normal: (v.normal.x, v.normal.y, 0.0) });
@@ -32,14 +39,19 @@ impl Tessellation {
         tessellation.indices = buffer.indices.into_iter().map(Into::into).collect();
         tessellation
     }
-    fn from_stroke_buffer(buffer: VertexBuffers<StrokeVertex>, colorer: Colorer) -> Self {
+    fn from_stroke_buffer(
+        buffer: VertexBuffers<StrokeVertex>,
+        colorer: Colorer,
+        uv: Option<UvTransform>,
+    ) -> Self {
         let mut tessellation = Tessellation::default();
         for v in buffer.vertices {
             let point = Point::from(v.position);
             let color = colorer.color(point);
-            tessellation
-                .vertices
-                .push(GpuVertex::from((point, color)));
+            tessellation.vertices.push(match uv {
+                Some(uv) => GpuVertex::from((point, color, uv.apply(point))),
+                None => GpuVertex::from((point, color)),
+            });
             tessellation
                 .normals
                 .push(GpuNormal { normal: (v.normal.x, v.normal.y, 0.0) });
@@ -50,12 +62,24 @@ impl Tessellation {
 }
 
 pub trait Tessellate {
-    fn tessellate_fill(&self, colorer: Colorer) -> Result<Tessellation>;
-    fn tessellate_stroke(&self, thickness: f32, colorer: Colorer) -> Result<Tessellation>;
+    fn tessellate_fill(&self, colorer: Colorer) -> Result<Tessellation> {
+        self.tessellate_fill_with_uv(colorer, None)
+    }
+    fn tessellate_fill_with_uv(&self, colorer: Colorer, uv: Option<UvTransform>) -> Result<Tessellation>;
+
+    fn tessellate_stroke(&self, thickness: f32, colorer: Colorer) -> Result<Tessellation> {
+        self.tessellate_stroke_with_style(thickness, &StrokeStyle::default(), colorer)
+    }
+    fn tessellate_stroke_with_style(
+        &self,
+        thickness: f32,
+        style: &StrokeStyle,
+        colorer: Colorer,
+    ) -> Result<Tessellation>;
 }
 
 impl Tessellate for Ellipse {
-    fn tessellate_fill(&self, colorer: Colorer) -> Result<Tessellation> {
+    fn tessellate_fill_with_uv(&self, colorer: Colorer, uv: Option<UvTransform>) -> Result<Tessellation> {
         let mut vertex_buffers: VertexBuffers<FillVertex> = VertexBuffers::new();
         match self.height {
             Some(height) => {
@@ -72,50 +96,121 @@ impl Tessellate for Ellipse {
                                           &mut simple_builder(&mut vertex_buffers));
             }
         };
-        Ok(Tessellation::from_fill_buffer(vertex_buffers, colorer))
+        Ok(Tessellation::from_fill_buffer(vertex_buffers, colorer, uv))
     }
-    fn tessellate_stroke(&self, thickness: f32, colorer: Colorer) -> Result<Tessellation> {
+    fn tessellate_stroke_with_style(
+        &self,
+        thickness: f32,
+        style: &StrokeStyle,
+        colorer: Colorer,
+    ) -> Result<Tessellation> {
+        let tolerance = self.tolerance.unwrap_or(ELLIPSE_TOLERANCE);
+
+        // Dashing needs a polyline to split into on/off runs; stroke the
+        // flattened outline through the same path `Poly` shapes take
+        // rather than teaching `basic_shapes::stroke_{ellipse,circle}`
+        // about dash patterns.
+        if let Some(dash) = &style.dash {
+            let points = flatten_ellipse(self, tolerance);
+            let mut vertex_buffers: VertexBuffers<StrokeVertex> = VertexBuffers::new();
+            let options = style.apply(StrokeOptions::default()
+                .with_line_width(thickness)
+                .with_tolerance(tolerance));
+            for run in dash_polyline(&points, true, dash) {
+                if run.len() < 2 {
+                    continue;
+                }
+                basic_shapes::stroke_polyline(run.into_iter().map(Into::into),
+                                              false,
+                                              &options,
+                                              &mut simple_builder(&mut vertex_buffers));
+            }
+            return Ok(Tessellation::from_stroke_buffer(vertex_buffers, colorer, None));
+        }
+
         let mut vertex_buffers: VertexBuffers<StrokeVertex> = VertexBuffers::new();
         match self.height {
             Some(height) => {
                 basic_shapes::stroke_ellipse(self.center.into(),
                                              Vec2::new(self.width, height),
                                              self.rotation,
-                                            &StrokeOptions::default()
+                                            &style.apply(StrokeOptions::default()
                                                  .with_line_width(thickness)
-                                                 .with_tolerance(self.tolerance
-                                                                     .unwrap_or(ELLIPSE_TOLERANCE)),
+                                                 .with_tolerance(tolerance)),
                                              &mut simple_builder(&mut vertex_buffers));
             }
             None => {
                 basic_shapes::stroke_circle(self.center.into(),
                                             self.width,
-                                            &StrokeOptions::default()
+                                            &style.apply(StrokeOptions::default()
                                                  .with_line_width(thickness)
-                                                 .with_tolerance(self.tolerance
-                                                                     .unwrap_or(ELLIPSE_TOLERANCE)),
+                                                 .with_tolerance(tolerance)),
                                             &mut simple_builder(&mut vertex_buffers));
             }
         };
-        Ok(Tessellation::from_stroke_buffer(vertex_buffers, colorer))
+        Ok(Tessellation::from_stroke_buffer(vertex_buffers, colorer, None))
     }
 }
 
+/// Samples an `Ellipse`'s boundary into a closed polyline, dense enough
+/// for `tolerance`, so it can be handed to the dash-splitting stroke path.
+fn flatten_ellipse(ellipse: &Ellipse, tolerance: f32) -> Vec<Point> {
+    let (rx, ry) = (ellipse.width, ellipse.height.unwrap_or(ellipse.width));
+    let max_radius = rx.max(ry).max(1.0);
+    let steps = ((max_radius / tolerance.max(0.0001)).sqrt().ceil() as usize)
+        .max(32)
+        .min(2048);
+    (0..steps)
+        .map(|i| {
+            let t = i as f32 / steps as f32 * std::f32::consts::PI * 2.0;
+            let (sin, cos) = (t.sin(), t.cos());
+            let (rot_sin, rot_cos) = (ellipse.rotation.sin(), ellipse.rotation.cos());
+            let local = (rx * cos, ry * sin);
+            Point {
+                x: ellipse.center.x + local.0 * rot_cos - local.1 * rot_sin,
+                y: ellipse.center.y + local.0 * rot_sin + local.1 * rot_cos,
+            }
+        })
+        .collect()
+}
+
 impl<P: Poly> Tessellate for P {
-    fn tessellate_fill(&self, colorer: Colorer) -> Result<Tessellation> {
+    fn tessellate_fill_with_uv(&self, colorer: Colorer, uv: Option<UvTransform>) -> Result<Tessellation> {
         let mut vertex_buffers: VertexBuffers<FillVertex> = VertexBuffers::new();
         basic_shapes::fill_polyline(self.vertices().into_iter().map(Into::into),
                                     &mut FillTessellator::new(),
                                     &FillOptions::default(),
                                     &mut simple_builder(&mut vertex_buffers))?;
-        Ok(Tessellation::from_fill_buffer(vertex_buffers, colorer))
+        Ok(Tessellation::from_fill_buffer(vertex_buffers, colorer, uv))
     }
-    fn tessellate_stroke(&self, thickness: f32, colorer: Colorer) -> Result<Tessellation> {
+    fn tessellate_stroke_with_style(
+        &self,
+        thickness: f32,
+        style: &StrokeStyle,
+        colorer: Colorer,
+    ) -> Result<Tessellation> {
+        let options = style.apply(StrokeOptions::default().with_line_width(thickness));
         let mut vertex_buffers: VertexBuffers<StrokeVertex> = VertexBuffers::new();
-        basic_shapes::stroke_polyline(self.vertices().into_iter().map(Into::into),
-                                      true,
-                                      &StrokeOptions::default(),
-                                      &mut simple_builder(&mut vertex_buffers));
-        Ok(Tessellation::from_stroke_buffer(vertex_buffers, colorer))
+        match &style.dash {
+            Some(dash) => {
+                let points = self.vertices();
+                for run in dash_polyline(&points, true, dash) {
+                    if run.len() < 2 {
+                        continue;
+                    }
+                    basic_shapes::stroke_polyline(run.into_iter().map(Into::into),
+                                                  false,
+                                                  &options,
+                                                  &mut simple_builder(&mut vertex_buffers));
+                }
+            }
+            None => {
+                basic_shapes::stroke_polyline(self.vertices().into_iter().map(Into::into),
+                                              true,
+                                              &options,
+                                              &mut simple_builder(&mut vertex_buffers));
+            }
+        }
+        Ok(Tessellation::from_stroke_buffer(vertex_buffers, colorer, None))
     }
 }
\ No newline at end of file
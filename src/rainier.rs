@@ -5,6 +5,7 @@ use crate::amicola::*;
 pub use crate::amicola::{Glsl, Polygon, Shader, UniformBuffer, V2, V4};
 pub use rand::{self, rngs::StdRng, Rng, SeedableRng};
 
+use crate::text::Font;
 use derive_more::DebugCustom;
 use failure::{Error, Fail};
 use glium::{backend::glutin::headless::Headless, program::Program};
@@ -119,6 +120,37 @@ pub trait Sketch {
     fn set_shader(&mut self, shader: Shader);
 
     fn fill(&mut self);
+
+    /// Lays out `s` at `size` points with its baseline origin at `origin`,
+    /// and fills each glyph with the sketch's current color and shader,
+    /// exactly as any other shape filled with `fill`. All of a glyph's
+    /// contours are submitted before its one `fill()` call, so glyphs with
+    /// counters (O, A, e, ...) get their holes punched by the winding rule
+    /// instead of each contour filling as its own separate solid shape.
+    fn text(&mut self, font: &Font, size: f32, origin: V2, s: &str) {
+        let glyphs = match crate::text::layout(font, size, origin, s) {
+            Ok(glyphs) => glyphs,
+            Err(_) => return,
+        };
+        for contours in glyphs {
+            let mut any = false;
+            for contour in contours {
+                let mut points = contour.into_iter();
+                let first = match points.next() {
+                    Some(p) => p,
+                    None => continue,
+                };
+                any = true;
+                self.move_to(first);
+                for p in points {
+                    self.line_to(p);
+                }
+            }
+            if any {
+                self.fill();
+            }
+        }
+    }
 }
 
 pub fn run<S, C: Composer<S>>(mut composer: C) {
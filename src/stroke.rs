@@ -0,0 +1,239 @@
+//! Stroke styling (caps, joins, miter limit, dashing) shared by every
+//! `Tessellate::tessellate_stroke` impl.
+
+use geom::Point;
+use lyon::tessellation::{LineCap as LyonLineCap, LineJoin as LyonLineJoin, StrokeOptions};
+
+/// How a stroke's unjoined ends are capped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+impl LineCap {
+    fn to_lyon(self) -> LyonLineCap {
+        match self {
+            LineCap::Butt => LyonLineCap::Butt,
+            LineCap::Round => LyonLineCap::Round,
+            LineCap::Square => LyonLineCap::Square,
+        }
+    }
+}
+
+/// How a stroke's segments are joined at interior vertices.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+impl LineJoin {
+    fn to_lyon(self) -> LyonLineJoin {
+        match self {
+            LineJoin::Miter => LyonLineJoin::Miter,
+            LineJoin::Round => LyonLineJoin::Round,
+            LineJoin::Bevel => LyonLineJoin::Bevel,
+        }
+    }
+}
+
+/// An alternating on/off dash array, in the same units as stroke
+/// `thickness`, plus a starting offset into the pattern.
+#[derive(Debug, Clone)]
+pub struct DashPattern {
+    /// Alternating on, off, on, off, ... run lengths. Must be non-empty and
+    /// sum to a positive length.
+    pub array: Vec<f32>,
+    /// Distance into the (cyclic) pattern the first vertex starts at.
+    pub offset: f32,
+}
+
+/// Line cap/join/miter limit and optional dashing for a stroke, mapped onto
+/// lyon's `StrokeOptions` by `tessellate_stroke`.
+#[derive(Debug, Clone)]
+pub struct StrokeStyle {
+    pub cap: LineCap,
+    pub join: LineJoin,
+    pub miter_limit: f32,
+    pub dash: Option<DashPattern>,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        StrokeStyle {
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 4.0,
+            dash: None,
+        }
+    }
+}
+
+impl StrokeStyle {
+    pub(crate) fn apply(&self, options: StrokeOptions) -> StrokeOptions {
+        options
+            .with_line_cap(self.cap.to_lyon())
+            .with_line_join(self.join.to_lyon())
+            .with_miter_limit(self.miter_limit)
+    }
+}
+
+/// Splits a polyline into the sub-polylines covered by a dash pattern's
+/// "on" runs, carrying dash phase across segment boundaries so corners
+/// dash continuously instead of restarting the pattern at each vertex.
+pub(crate) fn dash_polyline(points: &[Point], closed: bool, pattern: &DashPattern) -> Vec<Vec<Point>> {
+    // An odd-length array's on/off roles flip every time the pattern wraps
+    // (SVG/Canvas `stroke-dasharray` semantics), which is equivalent to
+    // cycling through the array concatenated with itself.
+    let array: Vec<f32> = if pattern.array.len() % 2 == 1 {
+        pattern.array.iter().chain(pattern.array.iter()).copied().collect()
+    } else {
+        pattern.array.clone()
+    };
+    let total: f32 = array.iter().sum();
+    if points.len() < 2 || total <= 0.0 {
+        return vec![points.to_vec()];
+    }
+
+    let mut vertices: Vec<Point> = points.to_vec();
+    if closed {
+        if let Some(&first) = points.first() {
+            vertices.push(first);
+        }
+    }
+
+    let mut runs = Vec::new();
+    let mut current_run: Vec<Point> = Vec::new();
+    let phase = pattern.offset.rem_euclid(total);
+    let (mut dash_index, mut remaining) = locate(&array, phase);
+    let mut is_on = dash_index % 2 == 0;
+    if is_on {
+        current_run.push(vertices[0]);
+    }
+
+    for window in vertices.windows(2) {
+        let (mut a, b) = (window[0], window[1]);
+        let mut segment_len = distance(a, b);
+
+        while segment_len > 0.0 {
+            if remaining >= segment_len {
+                remaining -= segment_len;
+                if is_on {
+                    current_run.push(b);
+                }
+                segment_len = 0.0;
+            } else {
+                let t = remaining / segment_len;
+                let split = lerp(a, b, t);
+                if is_on {
+                    current_run.push(split);
+                    runs.push(std::mem::replace(&mut current_run, Vec::new()));
+                } else {
+                    current_run.push(split);
+                }
+                segment_len -= remaining;
+                a = split;
+                is_on = !is_on;
+                dash_index = (dash_index + 1) % array.len();
+                remaining = array[dash_index];
+            }
+        }
+    }
+
+    if is_on && current_run.len() > 1 {
+        runs.push(current_run);
+    }
+
+    runs
+}
+
+fn locate(array: &[f32], mut phase: f32) -> (usize, f32) {
+    let mut index = 0;
+    loop {
+        let run = array[index];
+        if phase < run {
+            return (index, run - phase);
+        }
+        phase -= run;
+        index = (index + 1) % array.len();
+    }
+}
+
+fn distance(a: Point, b: Point) -> f32 {
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+}
+
+fn lerp(a: Point, b: Point, t: f32) -> Point {
+    Point { x: a.x + (b.x - a.x) * t, y: a.y + (b.y - a.y) * t }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(coords: &[(f32, f32)]) -> Vec<Point> {
+        coords.iter().map(|&(x, y)| Point { x, y }).collect()
+    }
+
+    fn lengths(runs: &[Vec<Point>]) -> Vec<f32> {
+        runs.iter()
+            .map(|run| run.windows(2).map(|w| distance(w[0], w[1])).sum())
+            .collect()
+    }
+
+    #[test]
+    fn even_length_pattern_alternates_on_off() {
+        let line = points(&[(0.0, 0.0), (10.0, 0.0)]);
+        let pattern = DashPattern { array: vec![2.0, 1.0], offset: 0.0 };
+        let runs = dash_polyline(&line, false, &pattern);
+        // on,off,on,off,on,off,on,off,on,off runs across a length-10 line
+        // with a 2-on/1-off pattern: 3 full on runs plus a trailing 1-unit
+        // partial (the line ends 1 unit into the 4th "on" run).
+        let run_lengths = lengths(&runs);
+        assert_eq!(run_lengths.len(), 4);
+        for len in &run_lengths[..3] {
+            assert!((len - 2.0).abs() < 0.001);
+        }
+        assert!((run_lengths[3] - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn odd_length_pattern_flips_on_off_every_cycle() {
+        // SVG/Canvas semantics: an odd-length array's roles flip each time
+        // the pattern wraps, equivalent to cycling through it doubled:
+        // [3, 3] on a length-9 line is on for 0..3, off for 3..6, on again
+        // for 6..9. Without that doubling, a single-element array's on/off
+        // role would never flip and the whole line would come out "on".
+        let line = points(&[(0.0, 0.0), (9.0, 0.0)]);
+        let pattern = DashPattern { array: vec![3.0], offset: 0.0 };
+        let runs = dash_polyline(&line, false, &pattern);
+        assert_eq!(runs.len(), 2);
+        let run_lengths = lengths(&runs);
+        assert!((run_lengths[0] - 3.0).abs() < 0.001);
+        assert!((run_lengths[1] - 3.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn phase_carries_across_segment_boundaries() {
+        // A dash phase that lands exactly on a vertex should continue
+        // uninterrupted into the next segment rather than restarting.
+        let line = points(&[(0.0, 0.0), (5.0, 0.0), (10.0, 0.0)]);
+        let pattern = DashPattern { array: vec![8.0, 2.0], offset: 0.0 };
+        let runs = dash_polyline(&line, false, &pattern);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].len(), 3);
+    }
+
+    #[test]
+    fn offset_starts_mid_pattern() {
+        let line = points(&[(0.0, 0.0), (10.0, 0.0)]);
+        let pattern = DashPattern { array: vec![2.0, 2.0], offset: 1.0 };
+        let runs = dash_polyline(&line, false, &pattern);
+        // Starting 1 unit into the first "on" run leaves only 1 unit of
+        // "on" length before the first gap.
+        assert!((lengths(&runs)[0] - 1.0).abs() < 0.001);
+    }
+}
@@ -0,0 +1,622 @@
+//! Imports SVG path data into this crate's `Poly`/`Ellipse` geometry so it
+//! can be rasterized through the existing `Tessellate` impls.
+
+use bezier::{self, FlattenPoint};
+use color::{Colorer, V4};
+use errors::Result;
+use failure::format_err;
+use geom::{Ellipse, Point, Poly};
+use gpu::tessellation::ELLIPSE_TOLERANCE;
+
+/// Recursion depth cap for `bezier::flatten_cubic`, generous enough for the
+/// tight curvature SVG paths can specify (unlike glyph outlines).
+const PATH_FLATTEN_DEPTH: u32 = 24;
+
+/// A shape imported from SVG, carrying the fill/stroke attributes parsed
+/// from its presentation attributes.
+pub struct SvgShape {
+    pub geometry: SvgGeometry,
+    pub fill: Option<Colorer>,
+    pub stroke: Option<(Colorer, f32)>,
+}
+
+/// The flattened geometry of an imported SVG element.
+pub enum SvgGeometry {
+    Poly(Polyline),
+    Ellipse(Ellipse),
+}
+
+/// A closed or open polyline flattened from SVG path/curve commands.
+///
+/// Implements `Poly` so it can be handed directly to `tessellate_fill`/
+/// `tessellate_stroke`.
+#[derive(Debug, Clone, Default)]
+pub struct Polyline(pub Vec<Point>);
+
+impl Poly for Polyline {
+    fn vertices(&self) -> Vec<Point> { self.0.clone() }
+}
+
+/// Tolerance, in SVG user units, used when flattening curves and arcs into
+/// polylines. Defaults to the same tolerance `Ellipse` tessellation uses.
+#[derive(Debug, Clone, Copy)]
+pub struct ImportOptions {
+    pub tolerance: f32,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self { ImportOptions { tolerance: ELLIPSE_TOLERANCE } }
+}
+
+/// Parses the `d` attribute of an SVG `<path>` into one or more flattened
+/// subpaths, handling `M`/`L`/`C`/`Q`/`A`/`Z` (and their lowercase, relative
+/// forms).
+pub fn parse_path_data(d: &str, options: ImportOptions) -> Result<Vec<Polyline>> {
+    PathParser::new(d, options).parse()
+}
+
+/// Flattens a `<rect>` into a closed `Polyline`.
+pub fn rect_to_polyline(x: f32, y: f32, width: f32, height: f32) -> Polyline {
+    Polyline(vec![
+        Point { x, y },
+        Point { x: x + width, y },
+        Point { x: x + width, y: y + height },
+        Point { x, y: y + height },
+    ])
+}
+
+/// Builds an `Ellipse` from a `<circle>` element's attributes.
+pub fn circle_to_ellipse(cx: f32, cy: f32, r: f32, tolerance: f32) -> Ellipse {
+    Ellipse {
+        center: Point { x: cx, y: cy },
+        width: r,
+        height: None,
+        rotation: 0.0,
+        tolerance: Some(tolerance),
+    }
+}
+
+/// Builds an `Ellipse` from an `<ellipse>` element's attributes.
+pub fn ellipse_to_ellipse(cx: f32, cy: f32, rx: f32, ry: f32, tolerance: f32) -> Ellipse {
+    Ellipse {
+        center: Point { x: cx, y: cy },
+        width: rx,
+        height: Some(ry),
+        rotation: 0.0,
+        tolerance: Some(tolerance),
+    }
+}
+
+/// Parses an SVG document's `<path>`/`<rect>`/`<circle>`/`<ellipse>`
+/// elements into shapes, in document order, along with their `fill`/
+/// `stroke`/`stroke-width` presentation attributes. A `<path>` whose `d`
+/// attribute contains several subpaths (multiple `M`s) yields one
+/// `SvgShape` per subpath, all sharing that `<path>`'s attributes.
+///
+/// This scans for elements by tag name rather than building a tree, so it
+/// has no notion of nesting, transforms, or inherited attributes; every
+/// element's `fill`/`stroke` must be set on the element itself.
+pub fn parse_document(svg: &str, options: ImportOptions) -> Result<Vec<SvgShape>> {
+    let mut shapes = Vec::new();
+    let mut rest = svg;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        if rest.starts_with("<?") || rest.starts_with("<!") || rest.starts_with("</") {
+            let end = rest.find('>').ok_or_else(|| format_err!("unterminated SVG tag"))?;
+            rest = &rest[end + 1..];
+            continue;
+        }
+        let end = rest.find('>').ok_or_else(|| format_err!("unterminated SVG tag"))?;
+        let tag = rest[1..end].trim().trim_end_matches('/');
+        let mut parts = tag.splitn(2, char::is_whitespace);
+        let name = parts.next().unwrap_or("");
+        let attrs = parse_attrs(parts.next().unwrap_or(""));
+        shapes.extend(parse_element(name, &attrs, options)?);
+        rest = &rest[end + 1..];
+    }
+    Ok(shapes)
+}
+
+/// Builds the `SvgShape`s for one already-tokenized element, or none for
+/// tags this importer doesn't recognize (groups, the `<svg>` root, ...).
+fn parse_element(name: &str, attrs: &[(String, String)], options: ImportOptions) -> Result<Vec<SvgShape>> {
+    let attr = |key: &str| attrs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+    let number = |key: &str| attr(key).and_then(|v| v.parse().ok()).unwrap_or(0.0);
+    let fill = attr("fill").and_then(parse_paint);
+    let stroke = attr("stroke")
+        .and_then(parse_paint)
+        .map(|colorer| (colorer, attr("stroke-width").and_then(|v| v.parse().ok()).unwrap_or(1.0)));
+
+    let geometries: Vec<SvgGeometry> = match name {
+        "path" => match attr("d") {
+            Some(d) => parse_path_data(d, options)?.into_iter().map(SvgGeometry::Poly).collect(),
+            None => Vec::new(),
+        },
+        "rect" => vec![SvgGeometry::Poly(rect_to_polyline(
+            number("x"),
+            number("y"),
+            number("width"),
+            number("height"),
+        ))],
+        "circle" => vec![SvgGeometry::Ellipse(circle_to_ellipse(
+            number("cx"),
+            number("cy"),
+            number("r"),
+            options.tolerance,
+        ))],
+        "ellipse" => vec![SvgGeometry::Ellipse(ellipse_to_ellipse(
+            number("cx"),
+            number("cy"),
+            number("rx"),
+            number("ry"),
+            options.tolerance,
+        ))],
+        _ => Vec::new(),
+    };
+
+    Ok(geometries
+        .into_iter()
+        .map(|geometry| SvgShape { geometry, fill: fill.clone(), stroke: stroke.clone() })
+        .collect())
+}
+
+/// Parses a tag's attribute source (everything after the tag name) into
+/// `name="value"` pairs, accepting either quote style.
+fn parse_attrs(s: &str) -> Vec<(String, String)> {
+    let mut attrs = Vec::new();
+    let mut chars = s.chars().peekable();
+    loop {
+        while matches!(chars.peek(), Some(c) if c.is_whitespace()) {
+            chars.next();
+        }
+        let mut name = String::new();
+        while matches!(chars.peek(), Some(c) if *c != '=' && !c.is_whitespace()) {
+            name.push(chars.next().unwrap());
+        }
+        if name.is_empty() {
+            break;
+        }
+        while matches!(chars.peek(), Some(c) if c.is_whitespace()) {
+            chars.next();
+        }
+        if chars.next() != Some('=') {
+            break;
+        }
+        while matches!(chars.peek(), Some(c) if c.is_whitespace()) {
+            chars.next();
+        }
+        let quote = match chars.next() {
+            Some(q) if q == '"' || q == '\'' => q,
+            _ => break,
+        };
+        let mut value = String::new();
+        for c in chars.by_ref() {
+            if c == quote {
+                break;
+            }
+            value.push(c);
+        }
+        attrs.push((name, value));
+    }
+    attrs
+}
+
+/// Parses a `fill`/`stroke` presentation attribute value into a solid
+/// `Colorer`. Only `none` and `#rrggbb`/`#rgb` hex colors are supported;
+/// anything else is treated as absent.
+pub fn parse_paint(value: &str) -> Option<Colorer> {
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("none") || value.is_empty() {
+        return None;
+    }
+    let hex = value.strip_prefix('#')?;
+    let (r, g, b) = match hex.len() {
+        6 => (
+            u8::from_str_radix(&hex[0..2], 16).ok()?,
+            u8::from_str_radix(&hex[2..4], 16).ok()?,
+            u8::from_str_radix(&hex[4..6], 16).ok()?,
+        ),
+        3 => {
+            let double = |c: &str| u8::from_str_radix(&c.repeat(2), 16).ok();
+            (
+                double(&hex[0..1])?,
+                double(&hex[1..2])?,
+                double(&hex[2..3])?,
+            )
+        }
+        _ => return None,
+    };
+    Some(Colorer::from(V4::new(
+        r as f32 / 255.0,
+        g as f32 / 255.0,
+        b as f32 / 255.0,
+        1.0,
+    )))
+}
+
+struct PathParser<'a> {
+    chars: std::str::Chars<'a>,
+    peeked: Option<char>,
+    options: ImportOptions,
+}
+
+impl<'a> PathParser<'a> {
+    fn new(d: &'a str, options: ImportOptions) -> Self {
+        PathParser { chars: d.chars(), peeked: None, options }
+    }
+
+    fn parse(mut self) -> Result<Vec<Polyline>> {
+        let mut subpaths = Vec::new();
+        let mut current: Vec<Point> = Vec::new();
+        let mut cursor = Point { x: 0.0, y: 0.0 };
+        let mut subpath_start = cursor;
+        let mut command = None;
+
+        self.skip_whitespace();
+        while let Some(c) = self.peek() {
+            if c.is_alphabetic() {
+                command = Some(c);
+                self.next();
+            }
+            let cmd = match command {
+                Some(cmd) => cmd,
+                None => break,
+            };
+            self.skip_whitespace();
+
+            match cmd.to_ascii_uppercase() {
+                'M' => {
+                    if !current.is_empty() {
+                        subpaths.push(Polyline(current.clone()));
+                        current.clear();
+                    }
+                    cursor = self.read_point(cmd.is_lowercase(), cursor)?;
+                    subpath_start = cursor;
+                    current.push(cursor);
+                    // Subsequent coordinate pairs without a new command
+                    // letter are implicit `L` commands.
+                    command = Some(if cmd.is_lowercase() { 'l' } else { 'L' });
+                }
+                'L' => {
+                    cursor = self.read_point(cmd.is_lowercase(), cursor)?;
+                    current.push(cursor);
+                }
+                'C' => {
+                    let c1 = self.read_point(cmd.is_lowercase(), cursor)?;
+                    let c2 = self.read_point(cmd.is_lowercase(), cursor)?;
+                    let end = self.read_point(cmd.is_lowercase(), cursor)?;
+                    bezier::flatten_cubic(cursor, c1, c2, end, self.options.tolerance, PATH_FLATTEN_DEPTH, &mut current);
+                    cursor = end;
+                }
+                'Q' => {
+                    let c1 = self.read_point(cmd.is_lowercase(), cursor)?;
+                    let end = self.read_point(cmd.is_lowercase(), cursor)?;
+                    bezier::flatten_quadratic(cursor, c1, end, self.options.tolerance, PATH_FLATTEN_DEPTH, &mut current);
+                    cursor = end;
+                }
+                'A' => {
+                    let rx = self.read_number()?;
+                    let ry = self.read_number()?;
+                    let x_axis_rotation = self.read_number()?;
+                    let large_arc = self.read_flag()?;
+                    let sweep = self.read_flag()?;
+                    let end = self.read_point(cmd.is_lowercase(), cursor)?;
+                    flatten_arc(
+                        cursor,
+                        rx,
+                        ry,
+                        x_axis_rotation.to_radians(),
+                        large_arc,
+                        sweep,
+                        end,
+                        self.options.tolerance,
+                        &mut current,
+                    );
+                    cursor = end;
+                }
+                'Z' => {
+                    if (cursor.x, cursor.y) != (subpath_start.x, subpath_start.y) {
+                        current.push(subpath_start);
+                    }
+                    cursor = subpath_start;
+                    subpaths.push(Polyline(current.clone()));
+                    current.clear();
+                    command = None;
+                }
+                _ => return Err(format_err!("unsupported SVG path command: {}", cmd)),
+            }
+            self.skip_whitespace();
+        }
+        if !current.is_empty() {
+            subpaths.push(Polyline(current));
+        }
+        Ok(subpaths)
+    }
+
+    fn read_point(&mut self, relative: bool, cursor: Point) -> Result<Point> {
+        let x = self.read_number()?;
+        let y = self.read_number()?;
+        Ok(if relative {
+            Point { x: cursor.x + x, y: cursor.y + y }
+        } else {
+            Point { x, y }
+        })
+    }
+
+    fn read_flag(&mut self) -> Result<bool> {
+        self.skip_separators();
+        match self.next() {
+            Some('0') => Ok(false),
+            Some('1') => Ok(true),
+            other => Err(format_err!("expected arc flag, found {:?}", other)),
+        }
+    }
+
+    fn read_number(&mut self) -> Result<f32> {
+        self.skip_separators();
+        let mut s = String::new();
+        if let Some(c) = self.peek() {
+            if c == '-' || c == '+' {
+                s.push(c);
+                self.next();
+            }
+        }
+        let mut seen_dot = false;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() {
+                s.push(c);
+                self.next();
+            } else if c == '.' && !seen_dot {
+                seen_dot = true;
+                s.push(c);
+                self.next();
+            } else {
+                break;
+            }
+        }
+        s.parse()
+            .map_err(|_| format_err!("expected number in SVG path data, found {:?}", s))
+    }
+
+    fn skip_separators(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || c == ',' {
+                self.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        if self.peeked.is_none() {
+            self.peeked = self.chars.next();
+        }
+        self.peeked
+    }
+
+    fn next(&mut self) -> Option<char> {
+        self.peek();
+        self.peeked.take()
+    }
+}
+
+impl FlattenPoint for Point {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Point { x: self.x + (other.x - self.x) * t, y: self.y + (other.y - self.y) * t }
+    }
+
+    fn deviation(self, a: Self, b: Self) -> f32 {
+        let dx = b.x - a.x;
+        let dy = b.y - a.y;
+        let len = (dx * dx + dy * dy).sqrt();
+        if len == 0.0 {
+            return ((self.x - a.x).powi(2) + (self.y - a.y).powi(2)).sqrt();
+        }
+        ((self.x - a.x) * dy - (self.y - a.y) * dx).abs() / len
+    }
+}
+
+/// Flattens an SVG elliptical arc (path command `A`) into a polyline,
+/// using the endpoint-to-center parameterization from SVG spec section
+/// F.6.5 to honor `x_axis_rotation` (radians), `large_arc`, and `sweep`.
+fn flatten_arc(
+    start: Point,
+    rx: f32,
+    ry: f32,
+    x_axis_rotation: f32,
+    large_arc: bool,
+    sweep: bool,
+    end: Point,
+    tolerance: f32,
+    out: &mut Vec<Point>,
+) {
+    if rx.abs() < 1e-6 || ry.abs() < 1e-6 || (start.x == end.x && start.y == end.y) {
+        out.push(end);
+        return;
+    }
+
+    let (sin_phi, cos_phi) = x_axis_rotation.sin_cos();
+    let (dx, dy) = ((start.x - end.x) / 2.0, (start.y - end.y) / 2.0);
+    let x1 = cos_phi * dx + sin_phi * dy;
+    let y1 = -sin_phi * dx + cos_phi * dy;
+
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        let scale = lambda.sqrt();
+        rx *= scale;
+        ry *= scale;
+    }
+
+    let sign = if large_arc != sweep { 1.0 } else { -1.0 };
+    let num = (rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1).max(0.0);
+    let den = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let co = sign * (num / den).sqrt();
+    let cx1 = co * rx * y1 / ry;
+    let cy1 = -co * ry * x1 / rx;
+
+    let center = Point {
+        x: cos_phi * cx1 - sin_phi * cy1 + (start.x + end.x) / 2.0,
+        y: sin_phi * cx1 + cos_phi * cy1 + (start.y + end.y) / 2.0,
+    };
+
+    let angle_start = angle_between((1.0, 0.0), ((x1 - cx1) / rx, (y1 - cy1) / ry));
+    let mut angle_extent = angle_between(
+        ((x1 - cx1) / rx, (y1 - cy1) / ry),
+        ((-x1 - cx1) / rx, (-y1 - cy1) / ry),
+    );
+    if !sweep && angle_extent > 0.0 {
+        angle_extent -= std::f32::consts::PI * 2.0;
+    } else if sweep && angle_extent < 0.0 {
+        angle_extent += std::f32::consts::PI * 2.0;
+    }
+
+    let max_radius = rx.max(ry).max(1.0);
+    let steps = ((angle_extent.abs() * max_radius / tolerance.max(0.0001)).sqrt().ceil() as usize)
+        .max(8)
+        .min(512);
+    for i in 1..=steps {
+        let t = i as f32 / steps as f32;
+        let theta = angle_start + angle_extent * t;
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        out.push(Point {
+            x: center.x + rx * cos_theta * cos_phi - ry * sin_theta * sin_phi,
+            y: center.y + rx * cos_theta * sin_phi + ry * sin_theta * cos_phi,
+        });
+    }
+    out.push(end);
+}
+
+/// Signed angle (radians) from vector `u` to vector `v`, in `(-pi, pi]`.
+fn angle_between(u: (f32, f32), v: (f32, f32)) -> f32 {
+    let sign = if u.0 * v.1 - u.1 * v.0 < 0.0 { -1.0 } else { 1.0 };
+    let dot = u.0 * v.0 + u.1 * v.1;
+    let len = ((u.0 * u.0 + u.1 * u.1) * (v.0 * v.0 + v.1 * v.1)).sqrt();
+    sign * (dot / len).max(-1.0).min(1.0).acos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool { (a - b).abs() < 0.01 }
+
+    #[test]
+    fn path_data_parses_a_closed_triangle() {
+        let subpaths = parse_path_data("M0,0 L10,0 L5,10 Z", ImportOptions::default()).unwrap();
+        assert_eq!(subpaths.len(), 1);
+        let points = &subpaths[0].0;
+        assert_eq!((points[0].x, points[0].y), (0.0, 0.0));
+        assert_eq!((points[1].x, points[1].y), (10.0, 0.0));
+        assert_eq!((points[2].x, points[2].y), (5.0, 10.0));
+        // Z closes back to the subpath's start.
+        {
+            let last = points.last().unwrap();
+            assert_eq!((last.x, last.y), (0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn path_data_implicit_lineto_and_relative_commands() {
+        // An `M` followed by a bare coordinate pair is an implicit `L`;
+        // lowercase commands are relative to the current point.
+        let subpaths = parse_path_data("M0,0 10,0 l0,10", ImportOptions::default()).unwrap();
+        let points = &subpaths[0].0;
+        assert_eq!((points[1].x, points[1].y), (10.0, 0.0));
+        assert_eq!((points[2].x, points[2].y), (10.0, 10.0));
+    }
+
+    #[test]
+    fn path_data_splits_on_each_moveto() {
+        let subpaths = parse_path_data("M0,0 L1,0 M5,5 L6,5", ImportOptions::default()).unwrap();
+        assert_eq!(subpaths.len(), 2);
+        assert_eq!((subpaths[1].0[0].x, subpaths[1].0[0].y), (5.0, 5.0));
+    }
+
+    #[test]
+    fn path_data_rejects_unsupported_commands() {
+        assert!(parse_path_data("M0,0 T10,10", ImportOptions::default()).is_err());
+    }
+
+    #[test]
+    fn flatten_cubic_of_a_straight_line_stays_on_the_line() {
+        let start = Point { x: 0.0, y: 0.0 };
+        let end = Point { x: 10.0, y: 0.0 };
+        let mut out = Vec::new();
+        // Control points on the start-end line: the curve degenerates to a
+        // straight segment, so every sampled point has y == 0.
+        bezier::flatten_cubic(
+            start,
+            Point { x: 3.0, y: 0.0 },
+            Point { x: 7.0, y: 0.0 },
+            end,
+            0.01,
+            PATH_FLATTEN_DEPTH,
+            &mut out,
+        );
+        assert!(out.iter().all(|p| close(p.y, 0.0)));
+        let last = out.last().unwrap();
+        assert_eq!((last.x, last.y), (end.x, end.y));
+    }
+
+    #[test]
+    fn flatten_quadratic_ends_exactly_on_the_endpoint() {
+        let mut out = Vec::new();
+        bezier::flatten_quadratic(
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 5.0, y: 10.0 },
+            Point { x: 10.0, y: 0.0 },
+            0.01,
+            PATH_FLATTEN_DEPTH,
+            &mut out,
+        );
+        let last = out.last().unwrap();
+        assert_eq!((last.x, last.y), (10.0, 0.0));
+        // The curve should bulge toward the control point.
+        assert!(out.iter().any(|p| p.y > 1.0));
+    }
+
+    #[test]
+    fn flatten_arc_quarter_circle_sweeps_through_the_quadrant() {
+        // A unit-radius quarter circle from (1, 0) to (0, 1), swept
+        // positively, should bulge away from the chord's midpoint (0.5,
+        // 0.5) in the +x/+y quadrant, and the far corner (1, 1) is outside
+        // the arc.
+        let start = Point { x: 1.0, y: 0.0 };
+        let end = Point { x: 0.0, y: 1.0 };
+        let mut out = Vec::new();
+        flatten_arc(start, 1.0, 1.0, 0.0, false, true, end, 0.01, &mut out);
+        let last = out.last().unwrap();
+        assert_eq!((last.x, last.y), (end.x, end.y));
+        for p in &out {
+            let dist_from_origin = (p.x * p.x + p.y * p.y).sqrt();
+            assert!(close(dist_from_origin, 1.0));
+        }
+    }
+
+    #[test]
+    fn flatten_arc_sweep_flag_picks_the_opposite_arc() {
+        let start = Point { x: 1.0, y: 0.0 };
+        let end = Point { x: 0.0, y: 1.0 };
+        let mut positive = Vec::new();
+        flatten_arc(start, 1.0, 1.0, 0.0, false, true, end, 0.01, &mut positive);
+        let mut negative = Vec::new();
+        flatten_arc(start, 1.0, 1.0, 0.0, false, false, end, 0.01, &mut negative);
+        // The two arcs take different paths around the circle, so they
+        // shouldn't sample the same interior points.
+        let mid_positive = positive[positive.len() / 2];
+        let mid_negative = negative[negative.len() / 2];
+        assert_ne!((mid_positive.x, mid_positive.y), (mid_negative.x, mid_negative.y));
+    }
+}
@@ -0,0 +1,154 @@
+//! Lays out and flattens glyph outlines so text can be rasterized through
+//! the same `Sketch::fill` path as any other shape.
+
+use crate::amicola::V2;
+use crate::bezier::{self, FlattenPoint};
+use failure::Error;
+pub use font_kit::font::Font;
+use font_kit::hinting::HintingOptions;
+use font_kit::outline::OutlineSink;
+use pathfinder_geometry::line_segment::LineSegment2F;
+use pathfinder_geometry::vector::Vector2F;
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Tolerance, in font units, used when flattening glyph curves to
+/// polylines.
+const GLYPH_TOLERANCE: f32 = 0.5;
+
+/// Recursion depth cap for `bezier::flatten_cubic`, matched to glyph
+/// outlines' typically gentle curvature.
+const GLYPH_FLATTEN_DEPTH: u32 = 16;
+
+/// One filled contour of a laid-out glyph, in sketch space (already scaled
+/// by point size and offset by the glyph's pen position).
+pub type Contour = Vec<V2>;
+
+/// Lays out `s` starting at `origin`, at `size` points, and returns each
+/// glyph's contours (grouped per glyph, so a filler can submit a whole
+/// glyph before closing its fill and get counters/holes right) with
+/// horizontal advance (and kerning, where the font provides it) already
+/// applied.
+pub fn layout(font: &Font, size: f32, origin: V2, s: &str) -> Result<Vec<Vec<Contour>>> {
+    let units_per_em = font.metrics().units_per_em as f32;
+    let scale = size / units_per_em;
+
+    let mut glyphs = Vec::new();
+    let mut pen_x = origin.x;
+    let mut prev_glyph = None;
+
+    for ch in s.chars() {
+        let glyph_id = match font.glyph_for_char(ch) {
+            Some(id) => id,
+            None => continue,
+        };
+
+        if let Some(prev_glyph) = prev_glyph {
+            if let Ok(kerning) = font.kerning_for_glyphs(prev_glyph, glyph_id) {
+                pen_x += kerning.x() * scale;
+            }
+        }
+
+        let mut sink = ContourSink::default();
+        font.outline(glyph_id, HintingOptions::None, &mut sink)?;
+
+        let pen = V2::new(pen_x, origin.y);
+        let contours = sink
+            .into_contours(scale)
+            .into_iter()
+            .map(|contour| {
+                contour
+                    .into_iter()
+                    .map(|p| V2::new(pen.x + p.x, pen.y - p.y))
+                    .collect()
+            })
+            .collect();
+        glyphs.push(contours);
+
+        pen_x += font.advance(glyph_id)?.x() * scale;
+        prev_glyph = Some(glyph_id);
+    }
+
+    Ok(glyphs)
+}
+
+/// Collects `font-kit`'s move/line/curve/close outline events into flattened
+/// polyline contours, in font units.
+#[derive(Default)]
+struct ContourSink {
+    contours: Vec<Vec<Vector2F>>,
+    current: Vec<Vector2F>,
+}
+
+impl ContourSink {
+    fn into_contours(mut self, scale: f32) -> Vec<Contour> {
+        if !self.current.is_empty() {
+            self.contours.push(self.current);
+        }
+        self.contours
+            .into_iter()
+            .map(|points| points.into_iter().map(|p| V2::new(p.x() * scale, p.y() * scale)).collect())
+            .collect()
+    }
+
+    fn last(&self) -> Vector2F {
+        *self.current.last().unwrap_or(&Vector2F::zero())
+    }
+}
+
+impl OutlineSink for ContourSink {
+    fn move_to(&mut self, to: Vector2F) {
+        if !self.current.is_empty() {
+            self.contours.push(std::mem::replace(&mut self.current, Vec::new()));
+        }
+        self.current.push(to);
+    }
+
+    fn line_to(&mut self, to: Vector2F) {
+        self.current.push(to);
+    }
+
+    fn quadratic_curve_to(&mut self, ctrl: Vector2F, to: Vector2F) {
+        bezier::flatten_quadratic(
+            self.last(),
+            ctrl,
+            to,
+            GLYPH_TOLERANCE,
+            GLYPH_FLATTEN_DEPTH,
+            &mut self.current,
+        );
+    }
+
+    fn cubic_curve_to(&mut self, ctrl: LineSegment2F, to: Vector2F) {
+        bezier::flatten_cubic(
+            self.last(),
+            ctrl.from(),
+            ctrl.to(),
+            to,
+            GLYPH_TOLERANCE,
+            GLYPH_FLATTEN_DEPTH,
+            &mut self.current,
+        );
+    }
+
+    fn close(&mut self) {
+        if let Some(&first) = self.current.first() {
+            self.current.push(first);
+        }
+    }
+}
+
+impl FlattenPoint for Vector2F {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Vector2F::lerp(self, other, t)
+    }
+
+    fn deviation(self, a: Self, b: Self) -> f32 {
+        let ab = b - a;
+        let len = ab.length();
+        if len == 0.0 {
+            return (self - a).length();
+        }
+        ((self - a).x() * ab.y() - (self - a).y() * ab.x()).abs() / len
+    }
+}